@@ -1,3 +1,20 @@
+// A byte-offset range into the source text, used to pinpoint where an `EvalErr`
+// happened. There's no lexer in this crate yet, so spans only come from hand-built
+// `Expr::Spanned` nodes; an error raised outside one carries `Span::DUMMY`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub const DUMMY: Span = Span { start: 0, end: 0 };
+
+    pub const fn new(start: usize, end: usize) -> Span {
+        Span { start, end }
+    }
+}
+
 #[derive(PartialEq, Debug, Eq)]
 pub struct Identifier(String);
 
@@ -7,7 +24,7 @@ impl Identifier {
     }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MathToken {
     Minus,
     Plus,
@@ -16,15 +33,14 @@ pub enum MathToken {
     Modulo,
 }
 
-// Need to handle Not
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum BoolToken {
     And,
     Or,
-    Not, // implementation neeeded
+    Not,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RelToken {
     Leq,
     Geq,
@@ -32,7 +48,7 @@ pub enum RelToken {
     Neq,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum VarToken {
     Assign,
     PlusEq,
@@ -40,7 +56,7 @@ pub enum VarToken {
     MulEq,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Op {
     MathOp(MathToken),
     BoolOp(BoolToken),
@@ -54,14 +70,17 @@ pub enum BoolState {
     False,
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// Note: there's no lexer/parser in this crate yet, so float literals can only be built
+// by hand (`Expr::Float(_)`) rather than parsed from source text.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Type {
     Int32,
+    Float,
     Bool,
     Void, // for functions
 }
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Param {
     name: String,
     param_type: Type,
@@ -71,9 +90,18 @@ impl Param {
     pub fn new(name: String, param_type: Type) -> Param {
         Param { name, param_type }
     }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn param_type(&self) -> &Type {
+        &self.param_type
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// `block` holds `Expr`, which can no longer derive `Eq` once `Float(f64)` exists.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Function {
     name: String,
     params: Vec<Param>,
@@ -90,20 +118,37 @@ impl Function {
             return_type,
         }
     }
+
+    pub(crate) fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub(crate) fn params(&self) -> &[Param] {
+        &self.params
+    }
+
+    pub(crate) fn block(&self) -> &[Expr] {
+        &self.block
+    }
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// `f64` isn't `Eq`, so `Value` can only derive `PartialEq` now that `Float` exists.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Value {
     Num(i32),
-    Var(String),
+    Float(f64),
     Bool(bool),
+    Void, // result of a function with no return value
 }
 
-#[derive(Debug, PartialEq, Eq)]
+// `f64` isn't `Eq`, so `Expr` can only derive `PartialEq` now that `Float` exists.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Expr {
     // Right-hand expressions
     BinOp(Box<Expr>, Op, Box<Expr>),
+    UnOp(Op, Box<Expr>),
     Num(i32),
+    Float(f64),
     Var(String),
     Bool(bool),
 
@@ -115,8 +160,20 @@ pub enum Expr {
     While(Box<Expr>, Vec<Expr>),
     Func(Function),
     Return(Box<Expr>),
+    Call(String, Vec<Expr>),
+    Break,
+    Continue,
+
+    // Attaches a source `Span` to the expression it wraps so an `EvalErr` raised while
+    // evaluating it can be pinpointed. The parser will eventually wrap every node it
+    // builds in one of these; until then, an error raised outside any of these just
+    // reports `Span::DUMMY`.
+    Spanned(Box<Expr>, Span),
 }
 
+// A sequence of expressions making up a function body, if-arm or loop body.
+pub type Block = Vec<Expr>;
+
 impl From<Expr> for i32 {
     fn from(e: Expr) -> i32 {
         match e {
@@ -143,3 +200,21 @@ impl From<Expr> for bool {
         }
     }
 }
+
+impl From<Expr> for f64 {
+    fn from(e: Expr) -> f64 {
+        match e {
+            Expr::Float(f) => f,
+            _ => panic!(),
+        }
+    }
+}
+
+impl From<Value> for i32 {
+    fn from(v: Value) -> i32 {
+        match v {
+            Value::Num(i) => i,
+            _ => panic!(),
+        }
+    }
+}
@@ -1,58 +1,177 @@
 use crate::ast::{
-    Value::{Bool, Num, Var},
+    Value::{Bool, Float, Num, Void},
     *,
 };
 use std::collections::HashMap;
 
-pub type Scope = HashMap<Value, Value>; 
+// Keyed by variable name rather than by `Value`: once `Value::Float` exists the values
+// themselves are no longer `Eq`/`Hash`, so they can't double as their own map key.
+pub type Scope = HashMap<String, Value>;
 pub type Context = Vec<Scope>; // Context is a stack of scopes
-pub type FuncContext = HashMap<String, Context>; // fn name, context
+
+type NativeFn = Box<dyn Fn(&[Value]) -> EvalRes<Value>>;
+
+// Resolves `Expr::Call` targets: user-defined `Function`s declared in the program, and
+// native Rust closures registered by the embedder via `register_fn`.
+pub struct Functions {
+    user: HashMap<String, Function>,
+    native: HashMap<String, NativeFn>,
+}
+
+impl Functions {
+    // Starts out empty so an embedder can `register_fn` before any program is loaded.
+    pub fn new() -> Functions {
+        Functions {
+            user: HashMap::new(),
+            native: HashMap::new(),
+        }
+    }
+
+    // Collects every top-level function declaration so calls can be resolved before
+    // (or in between) evaluating the rest of the program.
+    pub fn from_program(program: &[Expr]) -> Functions {
+        let mut funcs = Functions::new();
+        funcs.register_program(program);
+        funcs
+    }
+
+    fn register_program(&mut self, program: &[Expr]) {
+        for e in program {
+            if let Expr::Func(f) = e {
+                self.user.insert(f.name().to_string(), f.clone());
+            }
+        }
+    }
+
+    // Registers a native built-in (e.g. `print`, `abs`, `max`). Consulted once
+    // `Expr::Call` fails to find a matching user-defined `Function`.
+    pub fn register_fn(&mut self, name: &str, f: impl Fn(&[Value]) -> EvalRes<Value> + 'static) {
+        self.native.insert(name.to_string(), Box::new(f));
+    }
+}
+
+impl Default for Functions {
+    fn default() -> Functions {
+        Functions::new()
+    }
+}
 
 type EvalRes<T> = Result<T, EvalErr>;
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum EvalErr {
-    NotFound(String),
-    NotImplemented,
-    TypeMismatch(String),
-    WrongOp(String),
-    WrongType(String),
+    NotFound(String, Span),
+    NotImplemented(Span),
+    TypeMismatch(String, Span),
+    WrongOp(String, Span),
+    WrongType(String, Span),
+    ArgMismatch(String, Span),
+}
+
+impl EvalErr {
+    pub fn span(&self) -> Span {
+        match self {
+            EvalErr::NotFound(_, span)
+            | EvalErr::NotImplemented(span)
+            | EvalErr::TypeMismatch(_, span)
+            | EvalErr::WrongOp(_, span)
+            | EvalErr::WrongType(_, span)
+            | EvalErr::ArgMismatch(_, span) => *span,
+        }
+    }
+
+    // Re-stamps the error with a more precise span as it unwinds through a
+    // `Expr::Spanned` node closer to where it actually happened.
+    fn with_span(self, span: Span) -> EvalErr {
+        match self {
+            EvalErr::NotFound(msg, _) => EvalErr::NotFound(msg, span),
+            EvalErr::NotImplemented(_) => EvalErr::NotImplemented(span),
+            EvalErr::TypeMismatch(msg, _) => EvalErr::TypeMismatch(msg, span),
+            EvalErr::WrongOp(msg, _) => EvalErr::WrongOp(msg, span),
+            EvalErr::WrongType(msg, _) => EvalErr::WrongType(msg, span),
+            EvalErr::ArgMismatch(msg, _) => EvalErr::ArgMismatch(msg, span),
+        }
+    }
+}
+
+impl std::fmt::Display for EvalErr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let msg: &str = match self {
+            EvalErr::NotFound(msg, _) => msg,
+            EvalErr::NotImplemented(_) => "not implemented",
+            EvalErr::TypeMismatch(msg, _) => msg,
+            EvalErr::WrongOp(msg, _) => msg,
+            EvalErr::WrongType(msg, _) => msg,
+            EvalErr::ArgMismatch(msg, _) => msg,
+        };
+
+        // There's no lexer/parser to produce spans yet, so an error raised outside any
+        // `Expr::Spanned` node keeps `Span::DUMMY`; don't print that as a `0..0` range
+        // that would look like precise location data.
+        match self.span() {
+            Span::DUMMY => write!(f, "{}", msg),
+            Span { start, end } => write!(f, "{} at {}..{}", msg, start, end),
+        }
+    }
 }
 
+impl std::error::Error for EvalErr {}
+
+// Non-local control flow: a `Return`, `Break` or `Continue` unwinds through
+// `eval_expr`/`eval_block`/`eval_if` the same way an `EvalErr` does, just caught at a
+// different boundary (`eval_while` for loops, `eval_call` for function bodies) instead
+// of being reported to the caller.
+// Holds a `Value` in `Return`, which can no longer derive `Eq` once `Float(f64)` exists.
+#[derive(Debug, PartialEq)]
+pub enum Unwind {
+    Return(Value),
+    Break,
+    Continue,
+    Error(EvalErr),
+}
+
+impl From<EvalErr> for Unwind {
+    fn from(err: EvalErr) -> Unwind {
+        Unwind::Error(err)
+    }
+}
+
+type Flow<T> = Result<T, Unwind>;
+
 pub trait ContextMethods {
-    fn update_var(&mut self, key: &Value, val: &Value) -> EvalRes<Value>;
+    fn update_var(&mut self, key: &str, val: &Value) -> EvalRes<Value>;
     fn drop_current_scope(&mut self);
-    fn get_val(&mut self, key: &Value) -> EvalRes<Value>;
-    fn insert_to_current_scope(&mut self, key: &Value, val: &Value);
+    fn get_val(&mut self, key: &str) -> EvalRes<Value>;
+    fn insert_to_current_scope(&mut self, key: &str, val: &Value);
     fn new_scope(&mut self);
 }
 
 impl ContextMethods for Context {
-    fn update_var(&mut self, key: &Value, val: &Value) -> EvalRes<Value> {
+    fn update_var(&mut self, key: &str, val: &Value) -> EvalRes<Value> {
         for scope in self.iter_mut().rev() {
-            match scope.get(&key) {
+            match scope.get(key) {
                 Some(_) => {
-                    scope.insert(key.clone(), val.clone());
+                    scope.insert(key.to_string(), val.clone());
                     return Ok(val.clone())
                 }
                 None => continue,
             }
         }
 
-        Err(EvalErr::NotFound("Value not found in context.".to_string()))
+        Err(EvalErr::NotFound("Value not found in context.".to_string(), Span::DUMMY))
     }
 
     fn drop_current_scope(&mut self) {
         self.pop();
     }
 
-    fn get_val(&mut self, key: &Value) -> EvalRes<Value> {
-        let mut val_res: EvalRes<Value> = Err(EvalErr::NotFound("Key not found in context scopes".to_string()));
+    fn get_val(&mut self, key: &str) -> EvalRes<Value> {
+        let mut val_res: EvalRes<Value> = Err(EvalErr::NotFound("Key not found in context scopes".to_string(), Span::DUMMY));
 
         for scope in self.iter().rev() {
-            match scope.get(&key) {
+            match scope.get(key) {
                 Some(value) => {
-                    val_res = Ok(value.clone()); 
+                    val_res = Ok(value.clone());
                     break;
                 },
                 None => continue,
@@ -62,16 +181,16 @@ impl ContextMethods for Context {
         val_res
     }
 
-    fn insert_to_current_scope(&mut self, key: &Value, val: &Value) {
+    fn insert_to_current_scope(&mut self, key: &str, val: &Value) {
         let scope_opt = self.last_mut();
         match scope_opt {
-            Some(scope) => scope.insert(key.clone(), val.clone()),
+            Some(scope) => scope.insert(key.to_string(), val.clone()),
             None => panic!("There are no scopes in the context."),
         };
     }
-    
+
     fn new_scope(&mut self) {
-        let mut scope: Scope = HashMap::new();
+        let scope: Scope = HashMap::new();
         self.push(scope);
     }
 
@@ -88,7 +207,7 @@ fn eval_i32_expr(l: i32, op: Op, r: i32) -> EvalRes<Value> {
         Op::RelOp(RelToken::Geq) => Ok(Bool(l > r)),
         Op::RelOp(RelToken::Leq) => Ok(Bool(l < r)),
         Op::RelOp(RelToken::Neq) => Ok(Bool(l != r)),
-        _ => Err(EvalErr::WrongOp(String::from("Not an i32 operator."))),
+        _ => Err(EvalErr::WrongOp(String::from("Not an i32 operator."), Span::DUMMY)),
     }
 }
 
@@ -100,114 +219,391 @@ fn eval_bool_expr(l: bool, op: Op, r: bool) -> EvalRes<Value> {
         Op::RelOp(RelToken::Geq) => Ok(Bool(l > r)),
         Op::RelOp(RelToken::Leq) => Ok(Bool(l < r)),
         Op::RelOp(RelToken::Neq) => Ok(Bool(l != r)),
-        _ => Err(EvalErr::WrongOp(String::from("Not a boolean operator."))),
+        _ => Err(EvalErr::WrongOp(String::from("Not a boolean operator."), Span::DUMMY)),
+    }
+}
+
+fn eval_f64_expr(l: f64, op: Op, r: f64) -> EvalRes<Value> {
+    match op {
+        Op::MathOp(MathToken::Division) => Ok(Float(l / r)),
+        Op::MathOp(MathToken::Multiply) => Ok(Float(l * r)),
+        Op::MathOp(MathToken::Plus) => Ok(Float(l + r)),
+        Op::MathOp(MathToken::Minus) => Ok(Float(l - r)),
+        Op::MathOp(MathToken::Modulo) => Ok(Float(l % r)),
+        Op::RelOp(RelToken::Equal) => Ok(Bool(l == r)),
+        Op::RelOp(RelToken::Geq) => Ok(Bool(l > r)),
+        Op::RelOp(RelToken::Leq) => Ok(Bool(l < r)),
+        Op::RelOp(RelToken::Neq) => Ok(Bool(l != r)),
+        _ => Err(EvalErr::WrongOp(String::from("Not a float operator."), Span::DUMMY)),
     }
 }
 
-// Evaluates whether an expression is an i32 or bool operation.
-fn eval_bin_expr(l: Expr, op: Op, r: Expr, context: &mut Context) -> EvalRes<Value> {
-    let l_val = eval_expr(l, context)?;
-    let r_val = eval_expr(r, context)?;
+// Evaluates whether an expression is an i32, float or bool operation. A `Num` paired
+// with a `Float` is promoted to float; `Num op Num` always stays integer-exact.
+fn eval_bin_expr(l: Expr, op: Op, r: Expr, funcs: &Functions, context: &mut Context) -> Flow<Value> {
+    let l_val = eval_expr(l, funcs, context)?;
+    let r_val = eval_expr(r, funcs, context)?;
 
     match (l_val, r_val) {
-        (Num(l_val), Num(r_val)) => eval_i32_expr(l_val, op, r_val),
-        (Bool(l_val), Bool(r_val)) => eval_bool_expr(l_val, op, r_val),
-        _ => Err(EvalErr::TypeMismatch(String::from(
-            "Can not evaluate an operation between a bool and an i32.",
-        ))),
+        (Num(l_val), Num(r_val)) => Ok(eval_i32_expr(l_val, op, r_val)?),
+        (Bool(l_val), Bool(r_val)) => Ok(eval_bool_expr(l_val, op, r_val)?),
+        (Float(l_val), Float(r_val)) => Ok(eval_f64_expr(l_val, op, r_val)?),
+        (Num(l_val), Float(r_val)) => Ok(eval_f64_expr(l_val as f64, op, r_val)?),
+        (Float(l_val), Num(r_val)) => Ok(eval_f64_expr(l_val, op, r_val as f64)?),
+        _ => Err(EvalErr::TypeMismatch(
+            String::from("Can not evaluate an operation between these value types."),
+            Span::DUMMY,
+        )
+        .into()),
+    }
+}
+
+// Evaluates a unary operator: logical `Not` on a `Bool`, arithmetic negation on a `Num`
+// or `Float`. There's no lexer/parser in this crate yet, so `!flag`/`-x` can only be
+// built by hand (`Expr::UnOp(_, _)`) rather than parsed from source text.
+fn eval_un_expr(op: Op, val: Value) -> EvalRes<Value> {
+    match (op, val) {
+        (Op::BoolOp(BoolToken::Not), Bool(b)) => Ok(Bool(!b)),
+        (Op::MathOp(MathToken::Minus), Num(n)) => Ok(Num(-n)),
+        (Op::MathOp(MathToken::Minus), Float(f)) => Ok(Float(-f)),
+        (Op::BoolOp(BoolToken::Not), _) => {
+            Err(EvalErr::WrongType("`!` expects a bool operand.".to_string(), Span::DUMMY))
+        }
+        (Op::MathOp(MathToken::Minus), _) => Err(EvalErr::WrongType(
+            "Unary `-` expects a numeric operand.".to_string(),
+            Span::DUMMY,
+        )),
+        _ => Err(EvalErr::WrongOp("Not a unary operator.".to_string(), Span::DUMMY)),
     }
 }
 
 // Evaluates a complete binomial tree to a single integer or bool.
-pub fn eval_expr(e: Expr, context: &mut Context) -> EvalRes<Value> {
+pub fn eval_expr(e: Expr, funcs: &Functions, context: &mut Context) -> Flow<Value> {
     match e {
         Expr::Num(num) => Ok(Num(num)),
+        Expr::Float(f) => Ok(Float(f)),
         Expr::Bool(b) => Ok(Bool(b)),
-        Expr::Var(s) => context.get_val(&Var(s)),
-        Expr::BinOp(left, op, right) => eval_bin_expr(*left, op, *right, context),
+        Expr::Var(s) => Ok(context.get_val(&s)?),
+        Expr::BinOp(left, op, right) => eval_bin_expr(*left, op, *right, funcs, context),
+        Expr::UnOp(op, expr) => {
+            let val = eval_expr(*expr, funcs, context)?;
+            Ok(eval_un_expr(op, val)?)
+        }
         Expr::VarOp(var, op, expr) => {
-            let key = Var(String::from(*var));
-            let expr_val = eval_expr(*expr, context)?;
+            let key = String::from(*var);
+            let expr_val = eval_expr(*expr, funcs, context)?;
 
             match op {
-                Op::VarOp(VarToken::Assign) => context.update_var(&key, &expr_val),
-                _ => eval_var_op(&key, op, &expr_val, context),
+                Op::VarOp(VarToken::Assign) => Ok(context.update_var(&key, &expr_val)?),
+                _ => Ok(eval_var_op(&key, op, &expr_val, context)?),
             }
         },
-        Expr::Let(var, _, expr) => assign_var(*var, *expr, context), // ignore type for now
-        Expr::If(expr, block) => eval_if(*expr, block, context),
-        _ => Err(EvalErr::NotImplemented),
+        Expr::Let(var, _, expr) => assign_var(*var, *expr, funcs, context), // ignore type for now
+        Expr::If(expr, block) => eval_if(*expr, block, funcs, context),
+        Expr::While(expr, block) => eval_while(*expr, block, funcs, context),
+        // Only top-level declarations are registered (ahead of time, by
+        // `Functions::register_program`); a declaration reached here is not registered.
+        Expr::Func(_) => Ok(Void),
+        Expr::Call(name, args) => eval_call(name, args, funcs, context),
+        Expr::Return(expr) => Err(Unwind::Return(eval_expr(*expr, funcs, context)?)),
+        Expr::Break => Err(Unwind::Break),
+        Expr::Continue => Err(Unwind::Continue),
+        Expr::Spanned(inner, span) => eval_expr(*inner, funcs, context).map_err(|unwind| match unwind {
+            // Only stamp an error no inner `Spanned` node has stamped yet, so the
+            // innermost (most precise) span is the one that's kept.
+            Unwind::Error(err) if err.span() == Span::DUMMY => Unwind::Error(err.with_span(span)),
+            other => other,
+        }),
+        _ => Err(EvalErr::NotImplemented(Span::DUMMY).into()),
     }
 }
 
 // Assigns value to variable. Store it in current scope.
-fn assign_var(var: Expr, expr: Expr, context: &mut Context) -> EvalRes<Value> {
-    let id = Var(String::from(var));
-    let expr_val = eval_expr(expr, context)?;
+fn assign_var(var: Expr, expr: Expr, funcs: &Functions, context: &mut Context) -> Flow<Value> {
+    let id = String::from(var);
+    let expr_val = eval_expr(expr, funcs, context)?;
     context.insert_to_current_scope(&id, &expr_val);
     Ok(expr_val)
 }
 
-// Evaluates variable operations such as ´a += b´ etc.
-fn eval_var_op(key: &Value, op: Op, new_val: &Value, context: &mut Context) -> EvalRes<Value> {
-    let old_val: i32 = i32::from(context.get_val(key)?);
-    let expr_val: i32 = i32::from(new_val.clone());
+// Evaluates variable operations such as ´a += b´ etc. Promotes `Num`/`Float` the same
+// way `eval_bin_expr` does, rather than assuming an `i32` and panicking on a `Float`.
+fn eval_var_op(key: &str, op: Op, new_val: &Value, context: &mut Context) -> EvalRes<Value> {
+    let math_op = match op {
+        Op::VarOp(VarToken::PlusEq) => MathToken::Plus,
+        Op::VarOp(VarToken::MinEq) => MathToken::Minus,
+        Op::VarOp(VarToken::MulEq) => MathToken::Multiply,
+        _ => return Err(EvalErr::WrongOp("Not a variable operator.".to_string(), Span::DUMMY)),
+    };
 
-    match op {
-        Op::VarOp(VarToken::PlusEq) => {
-            let new_val = Num(old_val + expr_val);
-            context.update_var(key, &new_val)
-        },
-        Op::VarOp(VarToken::MinEq) => {
-            let new_val = Num(old_val - expr_val);
-            context.update_var(key, &new_val)
-        },
-        Op::VarOp(VarToken::MulEq) => {
-            let new_val = Num(old_val * expr_val);
-            context.update_var(key, &new_val)
-        },
-        _ => Err(EvalErr::WrongOp("Not a variable operator.".to_string()))
-    }
+    let old_val = context.get_val(key)?;
+    let combined = match (old_val, new_val.clone()) {
+        (Num(l), Num(r)) => eval_i32_expr(l, Op::MathOp(math_op), r)?,
+        (Float(l), Float(r)) => eval_f64_expr(l, Op::MathOp(math_op), r)?,
+        (Num(l), Float(r)) => eval_f64_expr(l as f64, Op::MathOp(math_op), r)?,
+        (Float(l), Num(r)) => eval_f64_expr(l, Op::MathOp(math_op), r as f64)?,
+        _ => {
+            return Err(EvalErr::WrongType(
+                "Compound assignment needs numeric operands.".to_string(),
+                Span::DUMMY,
+            ))
+        }
+    };
+
+    context.update_var(key, &combined)
 }
 
-fn eval_if(e: Expr, block: Block, context: &mut Context) -> EvalRes<Value> {
-    let condition = eval_expr(e, context)?;
-    let res: EvalRes<Value>;
+fn eval_if(e: Expr, block: Block, funcs: &Functions, context: &mut Context) -> Flow<Value> {
+    let condition = eval_expr(e, funcs, context)?;
 
     match condition {
-        Bool(true) => {
-            res = eval_block(block, context);
-        }
-        Bool(false) => res = Ok(Bool(false)),
-        _ => {
-            res = Err(EvalErr::WrongType(
-                "Cannot evaluate condition. Not a boolean expression.".to_string(),
-            ))
+        Bool(true) => eval_block(block, funcs, context),
+        Bool(false) => Ok(Bool(false)),
+        _ => Err(EvalErr::WrongType(
+            "Cannot evaluate condition. Not a boolean expression.".to_string(),
+            Span::DUMMY,
+        )
+        .into()),
+    }
+}
+
+// Drops the current scope when it goes out of scope, so a block's locals are popped
+// whether it finished normally, returned an error, or unwound via `Return`/`Break`/
+// `Continue`.
+struct ScopeGuard<'a> {
+    context: &'a mut Context,
+}
+
+impl<'a> std::ops::Deref for ScopeGuard<'a> {
+    type Target = Context;
+
+    fn deref(&self) -> &Context {
+        self.context
+    }
+}
+
+impl<'a> std::ops::DerefMut for ScopeGuard<'a> {
+    fn deref_mut(&mut self) -> &mut Context {
+        self.context
+    }
+}
+
+impl<'a> Drop for ScopeGuard<'a> {
+    fn drop(&mut self) {
+        self.context.drop_current_scope();
+    }
+}
+
+// Evaluates a complete block. Returns the value from the last instruction evaluated, or
+// immediately propagates the first `Return`/`Break`/`Continue`/error it runs into.
+pub fn eval_block(block: Block, funcs: &Functions, context: &mut Context) -> Flow<Value> {
+    context.new_scope();
+    let mut guard = ScopeGuard { context };
+
+    let mut res: Flow<Value> = Err(EvalErr::NotFound("No expressions found.".to_string(), Span::DUMMY).into());
+    for e in block {
+        res = eval_expr(e, funcs, &mut guard);
+        if res.is_err() {
+            break;
         }
     }
 
     res
 }
 
-// Evaluates a complete block. Returns the value from the last instruction evaluated.
-pub fn eval_block(block: Block, context: &mut Context) -> EvalRes<Value> {
+// Evaluates a `while` loop: re-checks the condition each iteration, runs the body, stops
+// on `Unwind::Break`, skips straight to the next condition check on `Unwind::Continue`,
+// and lets `Unwind::Return`/`Unwind::Error` bubble up to the enclosing function call.
+fn eval_while(cond: Expr, block: Block, funcs: &Functions, context: &mut Context) -> Flow<Value> {
+    loop {
+        match eval_expr(cond.clone(), funcs, context)? {
+            Bool(true) => {}
+            Bool(false) => break,
+            _ => {
+                return Err(EvalErr::WrongType(
+                    "Cannot evaluate condition. Not a boolean expression.".to_string(),
+                    Span::DUMMY,
+                )
+                .into())
+            }
+        }
+
+        match eval_block(block.clone(), funcs, context) {
+            Ok(_) => {}
+            Err(Unwind::Break) => break,
+            Err(Unwind::Continue) => continue,
+            Err(unwind) => return Err(unwind),
+        }
+    }
+
+    Ok(Void)
+}
+
+fn check_param_type(param: &Param, val: &Value) -> EvalRes<()> {
+    let matches = matches!(
+        (param.param_type(), val),
+        (Type::Int32, Num(_)) | (Type::Float, Float(_)) | (Type::Bool, Bool(_))
+    );
+
+    if matches {
+        Ok(())
+    } else {
+        Err(EvalErr::ArgMismatch(
+            format!(
+                "Argument `{}` expects a {:?}, got {:?}.",
+                param.name(),
+                param.param_type(),
+                val
+            ),
+            Span::DUMMY,
+        ))
+    }
+}
+
+// Calls a user-defined function if one is declared under `name`, otherwise falls back
+// to a native function registered via `Functions::register_fn`. Binds the evaluated
+// arguments to the user function's parameters and runs its block, unwrapping the
+// `Return`ed value (or `Void` if it fell off the end without returning). A function call
+// is its own unwind boundary: a `Break`/`Continue` that escapes the body (no enclosing
+// loop) is reported as an error rather than leaking into the caller's control flow.
+fn eval_call(name: String, args: Vec<Expr>, funcs: &Functions, context: &mut Context) -> Flow<Value> {
+    let mut arg_vals = Vec::with_capacity(args.len());
+    for arg in args {
+        arg_vals.push(eval_expr(arg, funcs, context)?);
+    }
+
+    if let Some(function) = funcs.user.get(&name).cloned() {
+        if arg_vals.len() != function.params().len() {
+            return Err(EvalErr::ArgMismatch(
+                format!(
+                    "Function `{}` expects {} argument(s), got {}.",
+                    name,
+                    function.params().len(),
+                    arg_vals.len()
+                ),
+                Span::DUMMY,
+            )
+            .into());
+        }
+
+        for (param, val) in function.params().iter().zip(&arg_vals) {
+            check_param_type(param, val)?;
+        }
+
+        // Run the call against the caller's real `context` instead of a cloned
+        // snapshot, so a write to a global from inside the body is a write to the
+        // actual global scope. The caller's own local scopes are stashed away first
+        // (the body should only see the global scope and its own parameters, not the
+        // caller's locals), then restored once the call returns.
+        let saved_scopes: Vec<Scope> = context.drain(1..).collect();
+        context.new_scope();
+        for (param, val) in function.params().iter().zip(arg_vals) {
+            context.insert_to_current_scope(param.name(), &val);
+        }
+
+        let result = if function.block().is_empty() {
+            Ok(Void) // an empty body falls off the end straight away
+        } else {
+            eval_block(function.block().to_vec(), funcs, context)
+        };
+        context.drop_current_scope(); // pop the parameter scope pushed above
+        context.splice(1..1, saved_scopes);
+
+        return match result {
+            Ok(_) => Ok(Void), // fell off the end without a `Return`
+            Err(Unwind::Return(val)) => Ok(val),
+            Err(Unwind::Break) | Err(Unwind::Continue) => Err(EvalErr::WrongOp(
+                "`break`/`continue` outside of a loop.".to_string(),
+                Span::DUMMY,
+            )
+            .into()),
+            Err(err @ Unwind::Error(_)) => Err(err),
+        };
+    }
+
+    if let Some(native) = funcs.native.get(&name) {
+        return Ok(native(&arg_vals)?);
+    }
+
+    Err(EvalErr::NotFound(format!("Function `{}` not found.", name), Span::DUMMY).into())
+}
+
+// Main entry: registers every top-level function, then evaluates the remaining
+// top-level expressions against a fresh context.
+pub fn eval_program(program: Vec<Expr>) -> Flow<Value> {
+    eval_program_with(program, Functions::new())
+}
+
+// Same as `eval_program`, but takes a `Functions` the caller has already set up (e.g. via
+// `register_fn`) instead of building an empty one. Lets an embedder register its host
+// functions before the program runs.
+pub fn eval_program_with(program: Vec<Expr>, mut funcs: Functions) -> Flow<Value> {
+    funcs.register_program(&program);
+    let mut context: Context = vec![];
     context.new_scope();
-    let mut res: EvalRes<Value> =
-        Err(EvalErr::NotFound("No expressions found.".to_string()));
 
-    for e in block {
-        res = eval_expr(e, context);
+    let mut res = Ok(Void);
+    for e in program {
+        if let Expr::Func(_) = e {
+            continue;
+        }
+        res = eval_expr(e, &funcs, &mut context);
     }
-    // Should drop the scope after here
-    // drop_current_scope(context);
+
     res
 }
 
-// TODO
-/* pub fn eval_function(f: Function, args: Args, context: &mut FuncContext) {
-    let mut fn_context: Context = vec![];
-    context.insert(f.name, fn_context);
-} */
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A function body that mutates a global must mutate the real global, not a
+    // snapshot that gets discarded when the call returns.
+    #[test]
+    fn function_call_mutates_global() {
+        let increment = Function::new(
+            "increment".to_string(),
+            vec![],
+            vec![Expr::VarOp(
+                Box::new(Expr::Var("counter".to_string())),
+                Op::VarOp(VarToken::PlusEq),
+                Box::new(Expr::Num(1)),
+            )],
+            Type::Void,
+        );
 
-// Main entry
-//pub fn eval_program() {}
\ No newline at end of file
+        let program = vec![
+            Expr::Let(
+                Box::new(Expr::Var("counter".to_string())),
+                Type::Int32,
+                Box::new(Expr::Num(0)),
+            ),
+            Expr::Func(increment),
+            Expr::Call("increment".to_string(), vec![]),
+            Expr::Var("counter".to_string()),
+        ];
+
+        assert_eq!(eval_program(program), Ok(Num(1)));
+    }
+
+    // `+=`/`-=`/`*=` on a Float used to panic via a hard-coded i32::from conversion
+    // instead of evaluating or returning an EvalErr.
+    #[test]
+    fn compound_assign_on_float_does_not_panic() {
+        let program = vec![
+            Expr::Let(
+                Box::new(Expr::Var("x".to_string())),
+                Type::Float,
+                Box::new(Expr::Float(1.0)),
+            ),
+            Expr::VarOp(
+                Box::new(Expr::Var("x".to_string())),
+                Op::VarOp(VarToken::PlusEq),
+                Box::new(Expr::Float(1.0)),
+            ),
+            Expr::Var("x".to_string()),
+        ];
+
+        assert_eq!(eval_program(program), Ok(Float(2.0)));
+    }
+}
\ No newline at end of file